@@ -0,0 +1,35 @@
+//! A query API for looking up blocks and transactions in O(1), modeled on
+//! OpenEthereum's `BlockProvider` trait. Without it, finding a block or
+//! transaction means linearly scanning `Blockchain::chain`; this is the
+//! foundation an RPC layer, explorer, or `verify_merkle_proof`-based SPV
+//! client would need to fetch data without doing that scan itself.
+
+use crate::{Block, Transaction};
+use chrono::{DateTime, Utc};
+
+/// A block's identifying metadata without its transactions - enough to
+/// verify chain linkage and proof of work without paying for the full
+/// transaction list.
+#[derive(Debug, Clone)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: DateTime<Utc>,
+    pub previous_hash: String,
+    pub hash: String,
+    pub nonce: u64,
+    pub merkle_root: String,
+}
+
+pub trait BlockProvider {
+    /// Whether a block with this hash has been mined.
+    fn is_known(&self, hash: &str) -> bool;
+    /// The full block with this hash, if any.
+    fn block_by_hash(&self, hash: &str) -> Option<&Block>;
+    /// The full block at this chain position, if any.
+    fn block_by_number(&self, index: u64) -> Option<&Block>;
+    /// The header of the block with this hash, if any.
+    fn block_header(&self, hash: &str) -> Option<BlockHeader>;
+    /// The transaction with this id and the index of the block it was mined
+    /// in, if it's been mined.
+    fn transaction(&self, tx_id: &str) -> Option<(u64, &Transaction)>;
+}
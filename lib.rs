@@ -1,7 +1,55 @@
 use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The sentinel `recent_blockhash` a transaction may reference before any
+/// real block has been mined, matching the genesis block's own
+/// `previous_hash`.
+const GENESIS_BLOCKHASH: &str = "0";
+
+/// How many of the most recently mined block hashes stay valid for new
+/// transactions to reference, following Solana's recent-blockhash scheme.
+/// This bounds how long a signed transaction remains replayable and keeps
+/// the seen-id set from growing without limit.
+const RECENT_BLOCKHASH_WINDOW: usize = 150;
+
+mod block_provider;
+mod merkle;
+mod payment_plan;
+mod storage;
+pub use block_provider::{BlockHeader, BlockProvider};
+pub use payment_plan::{Payment, PaymentPlan, Witness};
+pub use storage::{InMemoryStorage, SqliteStorage, Storage};
+
+/// The message a `Witness::Signature` signer must sign to release a
+/// `Conditional` transaction's escrowed funds, bound to both the
+/// transaction and the signer so one signer's witness can't be replayed
+/// against another contract.
+pub fn witness_apply_message(tx_id: &str, public_key: &str) -> Vec<u8> {
+    format!("apply:{}:{}", tx_id, public_key).into_bytes()
+}
+
+/// Verifies that `tx_id` is included under `root` given a Merkle `proof`
+/// produced by `Block::merkle_proof`. See that method for how the proof is
+/// shaped.
+pub fn verify_merkle_proof(tx_id: &str, proof: &[(String, bool)], root: &str) -> bool {
+    merkle::verify_proof(tx_id, proof, root)
+}
+
+/// Converts an `f64` literal (e.g. `100.0`) into the `Decimal` amounts
+/// stored everywhere else, following the xmr-btc-swap practice of keeping
+/// monetary values fixed-point rather than floating-point so balances never
+/// drift from accumulated rounding error. Meant for demo code and tests
+/// writing ordinary-looking literals; anything parsing user input should go
+/// through `Decimal::from_str` instead, which doesn't round-trip through
+/// binary floating point at all.
+pub fn amount(value: f64) -> Decimal {
+    Decimal::from_f64(value).expect("amount literal must be finite")
+}
 
 // Transaction types - showcasing different blockchain functionalities
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,71 +57,244 @@ pub enum TransactionType {
     Standard {
         from: String,
         to: String,
-        amount: f64,
+        amount: Decimal,
     },
     MultiSig {
         from: String,
         to: String,
-        amount: f64,
+        amount: Decimal,
         required_signatures: usize,
-        signatures: Vec<String>, // In real blockchain, these would be cryptographic signatures
+        signatures: Vec<SignatureEntry>,
     },
     TimeLocked {
         from: String,
         to: String,
-        amount: f64,
+        amount: Decimal,
         unlock_time: DateTime<Utc>,
     },
+    /// Funds are debited from `from` into escrow when mined, and only
+    /// credited to their eventual recipient once `plan` resolves - see
+    /// `PaymentPlan`. This generalizes `TimeLocked` to escrows,
+    /// refund-or-pay branches, and multi-party releases.
+    Conditional {
+        from: String,
+        amount: Decimal,
+        plan: PaymentPlan,
+    },
+}
+
+/// A single signer's contribution to a `MultiSig` transaction: an ed25519
+/// public key paired with the signature it produced over the transaction's
+/// signing hash. Keys and signatures are hex-encoded so the enclosing
+/// transaction stays plain-JSON serializable, matching the hex hashes used
+/// elsewhere in this crate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureEntry {
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// Generates a fresh ed25519 keypair for a participant (e.g. a multisig
+/// signer). Intended for demos and tests; a real deployment would load
+/// keys from secure storage instead.
+pub fn generate_keypair() -> SigningKey {
+    SigningKey::generate(&mut OsRng)
+}
+
+/// The exact bytes a `MultiSig` signer must sign: everything about the
+/// transaction except the signature set itself, so that adding or removing
+/// signatures never changes what was actually agreed to.
+pub fn multisig_signing_message(
+    from: &str,
+    to: &str,
+    amount: Decimal,
+    required_signatures: usize,
+    timestamp: DateTime<Utc>,
+    nonce: u64,
+    recent_blockhash: &str,
+) -> Vec<u8> {
+    format!(
+        "{}{}{}{}{}{}{}",
+        from, to, amount, required_signatures, timestamp, nonce, recent_blockhash
+    )
+    .into_bytes()
+}
+
+/// Signs `message` with `signing_key`, producing the `(public_key,
+/// signature)` pair that gets attached to a `MultiSig` transaction.
+pub fn sign_message(signing_key: &SigningKey, message: &[u8]) -> SignatureEntry {
+    let signature: Signature = signing_key.sign(message);
+    SignatureEntry {
+        public_key: hex::encode(signing_key.verifying_key().to_bytes()),
+        signature: hex::encode(signature.to_bytes()),
+    }
+}
+
+/// A transaction as submitted by a client: constructed, possibly signed,
+/// but not yet checked against the wallet's authorized signers or the
+/// chain's other rules. Borrowed from OpenEthereum's `UnverifiedTransaction`
+/// -> verified-transaction split, this is the only type
+/// `Blockchain::add_transaction` accepts, and it runs `verify()` before
+/// anything is added to `Blockchain::pending_transactions`. `Transaction`'s
+/// fields and `pending_transactions` itself are public, so code outside
+/// `add_transaction` can still build or push a `Transaction` directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnverifiedTransaction {
+    pub tx_type: TransactionType,
+    pub timestamp: DateTime<Utc>,
+    pub nonce: u64,
+    /// The hash of a recently mined block this transaction is valid
+    /// against. `Blockchain::add_transaction` rejects the transaction if
+    /// this hash has aged out of the recent-blockhash window.
+    pub recent_blockhash: String,
+}
+
+impl UnverifiedTransaction {
+    pub fn new(tx_type: TransactionType, nonce: u64, recent_blockhash: String) -> Self {
+        UnverifiedTransaction {
+            tx_type,
+            timestamp: Utc::now(),
+            nonce,
+            recent_blockhash,
+        }
+    }
+
+    /// The message signers must sign over for a `MultiSig` transaction.
+    /// Panics is avoided by returning `None` for non-multisig types, since
+    /// there is nothing to verify for them.
+    fn signing_message(&self) -> Option<Vec<u8>> {
+        match &self.tx_type {
+            TransactionType::MultiSig {
+                from,
+                to,
+                amount,
+                required_signatures,
+                ..
+            } => Some(multisig_signing_message(
+                from,
+                to,
+                *amount,
+                *required_signatures,
+                self.timestamp,
+                self.nonce,
+                &self.recent_blockhash,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Cryptographically verifies every signature on a `MultiSig`
+    /// transaction against the wallet's authorized signer set and the
+    /// required-signature threshold, then produces a `Transaction` that
+    /// the rest of the chain can trust. Non-multisig transactions pass
+    /// through unchanged, since they carry no signatures to check here.
+    pub fn verify(
+        self,
+        multisig_wallets: &HashMap<String, Vec<String>>,
+    ) -> Result<Transaction, String> {
+        if let TransactionType::MultiSig {
+            from,
+            required_signatures,
+            signatures,
+            ..
+        } = &self.tx_type
+        {
+            let authorized = multisig_wallets
+                .get(from)
+                .ok_or_else(|| format!("Unknown multisig wallet: {}", from))?;
+            let message = self
+                .signing_message()
+                .expect("MultiSig variant always has a signing message");
+
+            let mut valid_signers = HashSet::new();
+            for entry in signatures {
+                if !authorized.contains(&entry.public_key) {
+                    return Err(format!(
+                        "{} is not an authorized signer for {}",
+                        entry.public_key, from
+                    ));
+                }
+
+                let public_key_bytes: [u8; 32] = hex::decode(&entry.public_key)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| format!("malformed public key: {}", entry.public_key))?;
+                let verifying_key = VerifyingKey::from_bytes(&public_key_bytes)
+                    .map_err(|_| format!("invalid public key: {}", entry.public_key))?;
+
+                let signature_bytes: [u8; 64] = hex::decode(&entry.signature)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| format!("malformed signature from {}", entry.public_key))?;
+                let signature = Signature::from_bytes(&signature_bytes);
+
+                verifying_key
+                    .verify(&message, &signature)
+                    .map_err(|_| format!("invalid signature from {}", entry.public_key))?;
+
+                valid_signers.insert(entry.public_key.clone());
+            }
+
+            if valid_signers.len() < *required_signatures {
+                return Err(format!(
+                    "Insufficient valid signatures: {} required, {} verified",
+                    required_signatures,
+                    valid_signers.len()
+                ));
+            }
+        }
+
+        Ok(Transaction::from_verified(self))
+    }
 }
 
+/// A transaction that has passed `UnverifiedTransaction::verify` - its
+/// signatures (if any) are cryptographically valid and its signers are
+/// authorized. This is the only transaction type `Blockchain` stores or
+/// mines.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: String,
     pub tx_type: TransactionType,
     pub timestamp: DateTime<Utc>,
     pub nonce: u64,
+    pub recent_blockhash: String,
 }
 
 impl Transaction {
-    pub fn new(tx_type: TransactionType, nonce: u64) -> Self {
-        let timestamp = Utc::now();
-        let id = Self::calculate_hash(&tx_type, timestamp, nonce);
-        
+    fn from_verified(unverified: UnverifiedTransaction) -> Self {
+        let id = Self::calculate_hash(
+            &unverified.tx_type,
+            unverified.timestamp,
+            unverified.nonce,
+            &unverified.recent_blockhash,
+        );
         Transaction {
             id,
-            tx_type,
-            timestamp,
-            nonce,
+            tx_type: unverified.tx_type,
+            timestamp: unverified.timestamp,
+            nonce: unverified.nonce,
+            recent_blockhash: unverified.recent_blockhash,
         }
     }
 
-    fn calculate_hash(tx_type: &TransactionType, timestamp: DateTime<Utc>, nonce: u64) -> String {
-        let data = format!("{:?}{}{}", tx_type, timestamp, nonce);
+    fn calculate_hash(
+        tx_type: &TransactionType,
+        timestamp: DateTime<Utc>,
+        nonce: u64,
+        recent_blockhash: &str,
+    ) -> String {
+        let data = format!("{:?}{}{}{}", tx_type, timestamp, nonce, recent_blockhash);
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         hex::encode(hasher.finalize())
     }
 
     pub fn is_valid(&self, current_time: DateTime<Utc>) -> Result<(), String> {
-        match &self.tx_type {
-            TransactionType::MultiSig { required_signatures, signatures, .. } => {
-                if signatures.len() < *required_signatures {
-                    return Err(format!(
-                        "Insufficient signatures: {} required, {} provided",
-                        required_signatures,
-                        signatures.len()
-                    ));
-                }
-            }
-            TransactionType::TimeLocked { unlock_time, .. } => {
-                if current_time < *unlock_time {
-                    return Err(format!(
-                        "Transaction locked until {}",
-                        unlock_time
-                    ));
-                }
+        if let TransactionType::TimeLocked { unlock_time, .. } = &self.tx_type {
+            if current_time < *unlock_time {
+                return Err(format!("Transaction locked until {}", unlock_time));
             }
-            _ => {}
         }
         Ok(())
     }
@@ -87,11 +308,13 @@ pub struct Block {
     pub previous_hash: String,
     pub hash: String,
     pub nonce: u64,
+    pub merkle_root: String,
 }
 
 impl Block {
     pub fn new(index: u64, transactions: Vec<Transaction>, previous_hash: String) -> Self {
         let timestamp = Utc::now();
+        let merkle_root = merkle::root(&Self::leaf_ids(&transactions));
         let mut block = Block {
             index,
             timestamp,
@@ -99,34 +322,61 @@ impl Block {
             previous_hash,
             hash: String::new(),
             nonce: 0,
+            merkle_root,
         };
         block.hash = block.calculate_hash();
         block
     }
 
+    fn leaf_ids(transactions: &[Transaction]) -> Vec<String> {
+        transactions.iter().map(|tx| tx.id.clone()).collect()
+    }
+
     pub fn calculate_hash(&self) -> String {
         let data = format!(
             "{}{}{}{}{}",
-            self.index,
-            self.timestamp,
-            serde_json::to_string(&self.transactions).unwrap(),
-            self.previous_hash,
-            self.nonce
+            self.index, self.timestamp, self.merkle_root, self.previous_hash, self.nonce
         );
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         hex::encode(hasher.finalize())
     }
 
+    pub fn merkle_root(&self) -> &str {
+        &self.merkle_root
+    }
+
+    /// This block's header - its identifying metadata without the
+    /// transaction list. See `BlockHeader`.
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            hash: self.hash.clone(),
+            nonce: self.nonce,
+            merkle_root: self.merkle_root.clone(),
+        }
+    }
+
+    /// A Merkle proof that `tx_id` is one of this block's transactions:
+    /// the sibling hash at each level up to `merkle_root`, paired with
+    /// whether that sibling is on the left. Pass the result to
+    /// `verify_merkle_proof` to check it without needing the rest of the
+    /// block's transactions.
+    pub fn merkle_proof(&self, tx_id: &str) -> Option<Vec<(String, bool)>> {
+        merkle::proof(tx_id, &Self::leaf_ids(&self.transactions))
+    }
+
     // Proof of Work - mining with difficulty
     pub fn mine_block(&mut self, difficulty: usize) {
         let target = "0".repeat(difficulty);
-        
+
         while !self.hash.starts_with(&target) {
             self.nonce += 1;
             self.hash = self.calculate_hash();
         }
-        
+
         println!("Block mined: {} (nonce: {})", self.hash, self.nonce);
     }
 }
@@ -135,11 +385,39 @@ pub struct Blockchain {
     pub chain: Vec<Block>,
     pub difficulty: usize,
     pub pending_transactions: Vec<Transaction>,
-    pub balances: HashMap<String, f64>,
-    pub multisig_wallets: HashMap<String, Vec<String>>, // wallet_id -> authorized signers
+    pub balances: HashMap<String, Decimal>,
+    pub multisig_wallets: HashMap<String, Vec<String>>, // wallet_id -> authorized signer public keys (hex)
+    storage: Box<dyn Storage>,
+    // Hashes of the last RECENT_BLOCKHASH_WINDOW mined blocks, oldest first.
+    // A transaction is only accepted while its recent_blockhash is in here.
+    recent_blockhashes: VecDeque<String>,
+    // Transaction ids already seen, bucketed by the recent_blockhash they
+    // referenced, so the whole bucket can be dropped in one step once that
+    // blockhash ages out of the window.
+    seen_transaction_ids: HashMap<String, HashSet<String>>,
+    // Conditional transactions whose funds are in escrow, keyed by
+    // transaction id, pending their PaymentPlan resolving.
+    pending_contracts: HashMap<String, PendingContract>,
+    // Indexes backing `BlockProvider`: block hash -> position in `chain`,
+    // and transaction id -> position in `chain` of the block it was mined
+    // in. Populated as blocks are mined so lookups are O(1) instead of
+    // scanning `chain`.
+    block_hash_index: HashMap<String, u64>,
+    transaction_index: HashMap<String, u64>,
+}
+
+/// A `Conditional` transaction's funds sitting in escrow: the plan that
+/// must resolve to release them, and the signature witnesses gathered so
+/// far via `Blockchain::apply_witness`.
+struct PendingContract {
+    plan: PaymentPlan,
+    satisfied_signatures: HashSet<String>,
 }
 
 impl Blockchain {
+    /// Creates a fresh, purely in-memory blockchain. Used by the test suite
+    /// and anywhere persistence isn't needed; see `open` for a
+    /// SQLite-backed chain that survives restarts.
     pub fn new(difficulty: usize) -> Self {
         let mut blockchain = Blockchain {
             chain: Vec::new(),
@@ -147,80 +425,347 @@ impl Blockchain {
             pending_transactions: Vec::new(),
             balances: HashMap::new(),
             multisig_wallets: HashMap::new(),
+            storage: Box::new(InMemoryStorage::new()),
+            recent_blockhashes: VecDeque::new(),
+            seen_transaction_ids: HashMap::new(),
+            pending_contracts: HashMap::new(),
+            block_hash_index: HashMap::new(),
+            transaction_index: HashMap::new(),
         };
         blockchain.create_genesis_block();
         blockchain
     }
 
+    /// Opens (or creates) a SQLite-backed blockchain at `path`. If the
+    /// database already has blocks, the chain and wallet signer sets are
+    /// loaded and transactions are replayed to rebuild `balances`;
+    /// otherwise a fresh genesis block is mined and persisted.
+    pub fn open(path: &str, difficulty: usize) -> Result<Self, String> {
+        let storage = SqliteStorage::open(path)?;
+        let chain = storage.load_chain()?;
+        let multisig_wallets = storage.load_wallets()?;
+
+        let mut blockchain = Blockchain {
+            chain: Vec::new(),
+            difficulty,
+            pending_transactions: Vec::new(),
+            balances: HashMap::new(),
+            multisig_wallets,
+            storage: Box::new(storage),
+            recent_blockhashes: VecDeque::new(),
+            seen_transaction_ids: HashMap::new(),
+            pending_contracts: HashMap::new(),
+            block_hash_index: HashMap::new(),
+            transaction_index: HashMap::new(),
+        };
+
+        if chain.is_empty() {
+            blockchain.create_genesis_block();
+        } else {
+            for block in chain {
+                blockchain.apply_block_to_balances(&block)?;
+                blockchain.record_block_hash(&block);
+                blockchain.index_block(&block);
+                blockchain.chain.push(block);
+            }
+
+            // Signature witnesses applied before the restart don't show up
+            // anywhere in the replayed blocks themselves, so they have to be
+            // replayed separately to put `pending_contracts` back in the
+            // state `apply_witness` left it in.
+            for (tx_id, public_key, signature) in blockchain.storage.load_witnesses()? {
+                blockchain.replay_witness(&tx_id, &public_key, &signature)?;
+            }
+            blockchain.evaluate_pending_contracts(Utc::now())?;
+        }
+
+        Ok(blockchain)
+    }
+
+    /// The most recently mined block's hash - the value new transactions
+    /// should set as their `recent_blockhash`.
+    pub fn current_blockhash(&self) -> String {
+        self.chain
+            .last()
+            .map(|block| block.hash.clone())
+            .unwrap_or_else(|| GENESIS_BLOCKHASH.to_string())
+    }
+
     fn create_genesis_block(&mut self) {
-        let genesis_transaction = Transaction::new(
+        let genesis_transaction = UnverifiedTransaction::new(
             TransactionType::Standard {
                 from: "genesis".to_string(),
                 to: "genesis".to_string(),
-                amount: 0.0,
+                amount: Decimal::ZERO,
             },
             0,
-        );
-        
+            GENESIS_BLOCKHASH.to_string(),
+        )
+        .verify(&HashMap::new())
+        .expect("genesis transaction carries no signatures to verify");
+
         let mut genesis_block = Block::new(0, vec![genesis_transaction], "0".to_string());
         genesis_block.mine_block(self.difficulty);
+        self.storage
+            .save_block(&genesis_block)
+            .expect("failed to persist genesis block");
+        self.record_block_hash(&genesis_block);
+        self.index_block(&genesis_block);
         self.chain.push(genesis_block);
     }
 
-    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), String> {
-        // Validate transaction before adding
+    /// Debits `amount` from `address`'s balance, using checked arithmetic so
+    /// an overflow produces an error instead of silently wrapping or, as an
+    /// `f64` would, drifting into inexact territory.
+    fn debit(&mut self, address: &str, amount: Decimal) -> Result<(), String> {
+        let entry = self.balances.entry(address.to_string()).or_insert(Decimal::ZERO);
+        *entry = entry
+            .checked_sub(amount)
+            .ok_or_else(|| format!("balance underflow debiting {}", address))?;
+        Ok(())
+    }
+
+    /// Credits `amount` to `address`'s balance, using checked arithmetic so
+    /// an overflow produces an error instead of silently wrapping.
+    fn credit(&mut self, address: &str, amount: Decimal) -> Result<(), String> {
+        let entry = self.balances.entry(address.to_string()).or_insert(Decimal::ZERO);
+        *entry = entry
+            .checked_add(amount)
+            .ok_or_else(|| format!("balance overflow crediting {}", address))?;
+        Ok(())
+    }
+
+    /// Applies a block's transactions to `balances` without re-mining or
+    /// re-persisting it. Used to rebuild state when replaying a chain
+    /// loaded from storage.
+    fn apply_block_to_balances(&mut self, block: &Block) -> Result<(), String> {
+        for tx in &block.transactions {
+            match &tx.tx_type {
+                TransactionType::Standard { from, to, amount }
+                | TransactionType::MultiSig { from, to, amount, .. }
+                | TransactionType::TimeLocked { from, to, amount, .. } => {
+                    if from != "genesis" {
+                        self.debit(from, *amount)?;
+                    }
+                    self.credit(to, *amount)?;
+                }
+                TransactionType::Conditional { from, amount, plan } => {
+                    if from != "genesis" {
+                        self.debit(from, *amount)?;
+                    }
+                    self.pending_contracts.insert(
+                        tx.id.clone(),
+                        PendingContract {
+                            plan: plan.clone(),
+                            satisfied_signatures: HashSet::new(),
+                        },
+                    );
+                }
+            }
+        }
+        self.evaluate_pending_contracts(block.timestamp)
+    }
+
+    /// Re-checks every escrowed `Conditional` transaction's `PaymentPlan`
+    /// against `current_time` and the signature witnesses collected so
+    /// far, crediting the recipient and dropping the contract for any
+    /// plan that now resolves.
+    fn evaluate_pending_contracts(&mut self, current_time: DateTime<Utc>) -> Result<(), String> {
+        let resolved: Vec<(String, Payment)> = self
+            .pending_contracts
+            .iter()
+            .filter_map(|(tx_id, contract)| {
+                contract
+                    .plan
+                    .resolve(current_time, &contract.satisfied_signatures)
+                    .map(|payment| (tx_id.clone(), payment.clone()))
+            })
+            .collect();
+
+        for (tx_id, payment) in resolved {
+            self.credit(&payment.to, payment.amount)?;
+            self.pending_contracts.remove(&tx_id);
+        }
+        Ok(())
+    }
+
+    /// Verifies that `signature_hex` is a valid ed25519 signature by
+    /// `public_key_hex` over `witness_apply_message(tx_id, public_key_hex)`.
+    /// Shared by `apply_witness` and `replay_witness` so a witness loaded
+    /// back from storage is held to the same standard as one applied live.
+    fn verify_witness_signature(
+        tx_id: &str,
+        public_key_hex: &str,
+        signature_hex: &str,
+    ) -> Result<(), String> {
+        let public_key_bytes: [u8; 32] = hex::decode(public_key_hex)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("malformed public key: {}", public_key_hex))?;
+        let verifying_key = VerifyingKey::from_bytes(&public_key_bytes)
+            .map_err(|_| format!("invalid public key: {}", public_key_hex))?;
+
+        let signature_bytes: [u8; 64] = hex::decode(signature_hex)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "malformed witness signature".to_string())?;
+        let signature = Signature::from_bytes(&signature_bytes);
+
+        let message = witness_apply_message(tx_id, public_key_hex);
+        verifying_key
+            .verify(&message, &signature)
+            .map_err(|_| "invalid witness signature".to_string())
+    }
+
+    /// Submits a signature witness for an escrowed `Conditional`
+    /// transaction: verifies `signature_hex` was produced by
+    /// `public_key_hex` over `witness_apply_message(tx_id, public_key_hex)`,
+    /// persists it so it survives a restart, records it, and re-evaluates
+    /// the contract's `PaymentPlan`.
+    pub fn apply_witness(
+        &mut self,
+        tx_id: &str,
+        public_key_hex: &str,
+        signature_hex: &str,
+    ) -> Result<(), String> {
+        Self::verify_witness_signature(tx_id, public_key_hex, signature_hex)?;
+
+        let contract = self
+            .pending_contracts
+            .get_mut(tx_id)
+            .ok_or_else(|| format!("no pending contract for transaction {}", tx_id))?;
+        contract.satisfied_signatures.insert(public_key_hex.to_string());
+
+        self.storage.save_witness(tx_id, public_key_hex, signature_hex)?;
+        self.evaluate_pending_contracts(Utc::now())
+    }
+
+    /// Re-applies a witness loaded from storage while rebuilding
+    /// `pending_contracts` from a replayed chain in `open`. Unlike
+    /// `apply_witness`, a contract that's no longer pending isn't an error -
+    /// it just means another witness (e.g. a `Witness::Timestamp` that
+    /// already held at replay time) resolved the plan first - and the
+    /// witness isn't re-persisted, since it's already in storage.
+    fn replay_witness(
+        &mut self,
+        tx_id: &str,
+        public_key_hex: &str,
+        signature_hex: &str,
+    ) -> Result<(), String> {
+        Self::verify_witness_signature(tx_id, public_key_hex, signature_hex)?;
+
+        if let Some(contract) = self.pending_contracts.get_mut(tx_id) {
+            contract.satisfied_signatures.insert(public_key_hex.to_string());
+        }
+        Ok(())
+    }
+
+    /// Records a mined block's hash in the recent-blockhash window and its
+    /// transaction ids in the seen-id set, evicting the oldest blockhash
+    /// (and everything seen against it) once the window overflows.
+    fn record_block_hash(&mut self, block: &Block) {
+        self.recent_blockhashes.push_back(block.hash.clone());
+        if self.recent_blockhashes.len() > RECENT_BLOCKHASH_WINDOW {
+            if let Some(oldest) = self.recent_blockhashes.pop_front() {
+                self.seen_transaction_ids.remove(&oldest);
+            }
+        }
+
+        for tx in &block.transactions {
+            self.seen_transaction_ids
+                .entry(tx.recent_blockhash.clone())
+                .or_default()
+                .insert(tx.id.clone());
+        }
+    }
+
+    /// Records a mined block's hash and transaction ids in the `BlockProvider`
+    /// indexes so `block_by_hash` and `transaction` stay O(1).
+    fn index_block(&mut self, block: &Block) {
+        self.block_hash_index.insert(block.hash.clone(), block.index);
+        for tx in &block.transactions {
+            self.transaction_index.insert(tx.id.clone(), block.index);
+        }
+    }
+
+    pub fn add_transaction(&mut self, transaction: UnverifiedTransaction) -> Result<(), String> {
+        // Only a verified transaction - with cryptographically checked
+        // signatures, where applicable - may reach pending_transactions.
+        let transaction = transaction.verify(&self.multisig_wallets)?;
         transaction.is_valid(Utc::now())?;
-        
+
+        // Reject transactions referencing a blockhash we've already aged
+        // out of the recent window, same as Solana's "blockhash not found".
+        if !self.recent_blockhashes.contains(&transaction.recent_blockhash) {
+            return Err(format!(
+                "blockhash too old / unknown: {}",
+                transaction.recent_blockhash
+            ));
+        }
+
+        // Reject a transaction id we've already accepted against this
+        // blockhash - a signed transaction can only ever be mined once.
+        let already_seen = self
+            .seen_transaction_ids
+            .get(&transaction.recent_blockhash)
+            .is_some_and(|seen| seen.contains(&transaction.id));
+        if already_seen {
+            return Err(format!("replay detected: transaction {} already seen", transaction.id));
+        }
+
         // Check balance for non-genesis transactions
         match &transaction.tx_type {
             TransactionType::Standard { from, amount, .. }
             | TransactionType::MultiSig { from, amount, .. }
-            | TransactionType::TimeLocked { from, amount, .. } => {
+            | TransactionType::TimeLocked { from, amount, .. }
+            | TransactionType::Conditional { from, amount, .. } => {
                 if from != "genesis" {
-                    let balance = self.balances.get(from).unwrap_or(&0.0);
-                    if balance < amount {
+                    let balance = self.balances.get(from).copied().unwrap_or(Decimal::ZERO);
+                    if balance < *amount {
                         return Err(format!("Insufficient balance: {} has {}", from, balance));
                     }
                 }
             }
         }
-        
+
+        self.seen_transaction_ids
+            .entry(transaction.recent_blockhash.clone())
+            .or_default()
+            .insert(transaction.id.clone());
         self.pending_transactions.push(transaction);
         Ok(())
     }
 
-    pub fn mine_pending_transactions(&mut self) {
+    pub fn mine_pending_transactions(&mut self) -> Result<(), String> {
         if self.pending_transactions.is_empty() {
             println!("No transactions to mine");
-            return;
+            return Ok(());
         }
 
         let previous_hash = self.chain.last().unwrap().hash.clone();
         let index = self.chain.len() as u64;
-        
+
         let mut block = Block::new(index, self.pending_transactions.clone(), previous_hash);
         block.mine_block(self.difficulty);
-        
-        // Update balances
-        for tx in &block.transactions {
-            match &tx.tx_type {
-                TransactionType::Standard { from, to, amount }
-                | TransactionType::MultiSig { from, to, amount, .. }
-                | TransactionType::TimeLocked { from, to, amount, .. } => {
-                    if from != "genesis" {
-                        *self.balances.entry(from.clone()).or_insert(0.0) -= amount;
-                    }
-                    *self.balances.entry(to.clone()).or_insert(0.0) += amount;
-                }
-            }
-        }
-        
+
+        self.storage.save_block(&block)?;
+        self.apply_block_to_balances(&block)?;
+        self.record_block_hash(&block);
+        self.index_block(&block);
+
         self.chain.push(block);
         self.pending_transactions.clear();
+        Ok(())
     }
 
-    pub fn create_multisig_wallet(&mut self, wallet_id: String, signers: Vec<String>) {
-        self.multisig_wallets.insert(wallet_id, signers);
+    pub fn create_multisig_wallet(
+        &mut self,
+        wallet_id: String,
+        authorized_signers: Vec<String>,
+    ) -> Result<(), String> {
+        self.storage.save_wallet(&wallet_id, &authorized_signers)?;
+        self.multisig_wallets.insert(wallet_id, authorized_signers);
+        Ok(())
     }
 
     pub fn is_chain_valid(&self) -> bool {
@@ -234,6 +779,13 @@ impl Blockchain {
                 return false;
             }
 
+            // Verify the Merkle root matches the block's actual transactions
+            let expected_root = merkle::root(&Block::leaf_ids(&current_block.transactions));
+            if current_block.merkle_root != expected_root {
+                println!("Block {} Merkle root is invalid", i);
+                return false;
+            }
+
             // Verify chain linkage
             if current_block.previous_hash != previous_block.hash {
                 println!("Block {} is not properly linked", i);
@@ -249,8 +801,8 @@ impl Blockchain {
         true
     }
 
-    pub fn get_balance(&self, address: &str) -> f64 {
-        *self.balances.get(address).unwrap_or(&0.0)
+    pub fn get_balance(&self, address: &str) -> Decimal {
+        self.balances.get(address).copied().unwrap_or(Decimal::ZERO)
     }
 
     pub fn print_chain(&self) {
@@ -274,6 +826,32 @@ impl Blockchain {
     }
 }
 
+impl BlockProvider for Blockchain {
+    fn is_known(&self, hash: &str) -> bool {
+        self.block_hash_index.contains_key(hash)
+    }
+
+    fn block_by_hash(&self, hash: &str) -> Option<&Block> {
+        let &index = self.block_hash_index.get(hash)?;
+        self.chain.get(index as usize)
+    }
+
+    fn block_by_number(&self, index: u64) -> Option<&Block> {
+        self.chain.get(index as usize)
+    }
+
+    fn block_header(&self, hash: &str) -> Option<BlockHeader> {
+        self.block_by_hash(hash).map(Block::header)
+    }
+
+    fn transaction(&self, tx_id: &str) -> Option<(u64, &Transaction)> {
+        let &index = self.transaction_index.get(tx_id)?;
+        let block = self.chain.get(index as usize)?;
+        let transaction = block.transactions.iter().find(|tx| tx.id == tx_id)?;
+        Some((index, transaction))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,78 +866,443 @@ mod tests {
     #[test]
     fn test_standard_transaction() {
         let mut blockchain = Blockchain::new(2);
-        
+
         // Add initial funds
-        let tx1 = Transaction::new(
+        let tx1 = UnverifiedTransaction::new(
             TransactionType::Standard {
                 from: "genesis".to_string(),
                 to: "Alice".to_string(),
-                amount: 100.0,
+                amount: amount(100.0),
             },
             1,
+            blockchain.current_blockhash(),
         );
         blockchain.add_transaction(tx1).unwrap();
-        blockchain.mine_pending_transactions();
-        
-        assert_eq!(blockchain.get_balance("Alice"), 100.0);
+        blockchain.mine_pending_transactions().unwrap();
+
+        assert_eq!(blockchain.get_balance("Alice"), amount(100.0));
     }
 
     #[test]
     fn test_multisig_transaction() {
         let mut blockchain = Blockchain::new(2);
-        
-        // Create multisig wallet
+
+        // Each signer gets a real keypair; the wallet is authorized by
+        // public key, not by name.
+        let alice_key = generate_keypair();
+        let bob_key = generate_keypair();
+        let charlie_key = generate_keypair();
         blockchain.create_multisig_wallet(
             "vault".to_string(),
-            vec!["Alice".to_string(), "Bob".to_string(), "Charlie".to_string()],
-        );
-        
+            vec![
+                hex::encode(alice_key.verifying_key().to_bytes()),
+                hex::encode(bob_key.verifying_key().to_bytes()),
+                hex::encode(charlie_key.verifying_key().to_bytes()),
+            ],
+        ).unwrap();
+
         // Fund the vault
-        let tx1 = Transaction::new(
+        let tx1 = UnverifiedTransaction::new(
             TransactionType::Standard {
                 from: "genesis".to_string(),
                 to: "vault".to_string(),
-                amount: 1000.0,
+                amount: amount(1000.0),
             },
             1,
+            blockchain.current_blockhash(),
         );
         blockchain.add_transaction(tx1).unwrap();
-        blockchain.mine_pending_transactions();
-        
+        blockchain.mine_pending_transactions().unwrap();
+
         // Create multisig transaction with 2 of 3 signatures
-        let tx2 = Transaction::new(
-            TransactionType::MultiSig {
-                from: "vault".to_string(),
-                to: "Dave".to_string(),
-                amount: 500.0,
-                required_signatures: 2,
-                signatures: vec!["Alice".to_string(), "Bob".to_string()],
-            },
-            2,
+        let from = "vault".to_string();
+        let to = "Dave".to_string();
+        let transfer_amount = amount(500.0);
+        let required_signatures = 2;
+        let timestamp = Utc::now();
+        let nonce = 2;
+        let recent_blockhash = blockchain.current_blockhash();
+        let message = multisig_signing_message(
+            &from,
+            &to,
+            transfer_amount,
+            required_signatures,
+            timestamp,
+            nonce,
+            &recent_blockhash,
         );
+
+        let tx2 = UnverifiedTransaction {
+            tx_type: TransactionType::MultiSig {
+                from,
+                to,
+                amount: transfer_amount,
+                required_signatures,
+                signatures: vec![
+                    sign_message(&alice_key, &message),
+                    sign_message(&bob_key, &message),
+                ],
+            },
+            timestamp,
+            nonce,
+            recent_blockhash,
+        };
         blockchain.add_transaction(tx2).unwrap();
-        blockchain.mine_pending_transactions();
-        
-        assert_eq!(blockchain.get_balance("Dave"), 500.0);
-        assert_eq!(blockchain.get_balance("vault"), 500.0);
+        blockchain.mine_pending_transactions().unwrap();
+
+        assert_eq!(blockchain.get_balance("Dave"), amount(500.0));
+        assert_eq!(blockchain.get_balance("vault"), amount(500.0));
+    }
+
+    #[test]
+    fn test_multisig_rejects_unauthorized_signer() {
+        let mut blockchain = Blockchain::new(2);
+
+        let alice_key = generate_keypair();
+        let mallory_key = generate_keypair();
+        blockchain.create_multisig_wallet(
+            "vault".to_string(),
+            vec![hex::encode(alice_key.verifying_key().to_bytes())],
+        ).unwrap();
+
+        let tx1 = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "vault".to_string(),
+                amount: amount(1000.0),
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(tx1).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        let from = "vault".to_string();
+        let to = "Dave".to_string();
+        let amount = amount(500.0);
+        let required_signatures = 1;
+        let timestamp = Utc::now();
+        let nonce = 2;
+        let recent_blockhash = blockchain.current_blockhash();
+        let message = multisig_signing_message(
+            &from,
+            &to,
+            amount,
+            required_signatures,
+            timestamp,
+            nonce,
+            &recent_blockhash,
+        );
+
+        let tx2 = UnverifiedTransaction {
+            tx_type: TransactionType::MultiSig {
+                from,
+                to,
+                amount,
+                required_signatures,
+                signatures: vec![sign_message(&mallory_key, &message)],
+            },
+            timestamp,
+            nonce,
+            recent_blockhash,
+        };
+
+        assert!(blockchain.add_transaction(tx2).is_err());
     }
 
     #[test]
     fn test_time_locked_transaction() {
         let mut blockchain = Blockchain::new(2);
-        
+
         // Create time-locked transaction (unlocks in the past for testing)
         let unlock_time = Utc::now() - chrono::Duration::hours(1);
-        let tx = Transaction::new(
+        let tx = UnverifiedTransaction::new(
             TransactionType::TimeLocked {
                 from: "genesis".to_string(),
                 to: "Alice".to_string(),
-                amount: 100.0,
+                amount: amount(100.0),
                 unlock_time,
             },
             1,
+            blockchain.current_blockhash(),
         );
-        
+
         assert!(blockchain.add_transaction(tx).is_ok());
     }
+
+    #[test]
+    fn test_replay_of_same_transaction_is_rejected() {
+        let mut blockchain = Blockchain::new(2);
+
+        let tx1 = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Alice".to_string(),
+                amount: amount(100.0),
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        let replay = tx1.clone();
+
+        blockchain.add_transaction(tx1).unwrap();
+        assert!(blockchain.add_transaction(replay).is_err());
+    }
+
+    #[test]
+    fn test_unknown_blockhash_is_rejected() {
+        let mut blockchain = Blockchain::new(2);
+
+        let tx = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Alice".to_string(),
+                amount: amount(100.0),
+            },
+            1,
+            "not-a-real-block-hash".to_string(),
+        );
+
+        assert!(blockchain.add_transaction(tx).is_err());
+    }
+
+    #[test]
+    fn test_conditional_transaction_releases_after_timestamp() {
+        let mut blockchain = Blockchain::new(2);
+
+        let fund = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Escrow".to_string(),
+                amount: amount(100.0),
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(fund).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        let plan = PaymentPlan::After(
+            Witness::Timestamp(Utc::now() - chrono::Duration::hours(1)),
+            Box::new(PaymentPlan::Payment(Payment {
+                amount: amount(100.0),
+                to: "Alice".to_string(),
+            })),
+        );
+        let conditional = UnverifiedTransaction::new(
+            TransactionType::Conditional {
+                from: "Escrow".to_string(),
+                amount: amount(100.0),
+                plan,
+            },
+            2,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(conditional).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        // The witness timestamp is already in the past, so mining the
+        // block that escrows the funds also resolves the plan.
+        assert_eq!(blockchain.get_balance("Alice"), amount(100.0));
+        assert_eq!(blockchain.get_balance("Escrow"), amount(0.0));
+    }
+
+    #[test]
+    fn test_conditional_transaction_releases_on_signature_witness() {
+        let mut blockchain = Blockchain::new(2);
+
+        let fund = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Escrow".to_string(),
+                amount: amount(100.0),
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(fund).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        let arbiter_key = generate_keypair();
+        let arbiter_public_key = hex::encode(arbiter_key.verifying_key().to_bytes());
+
+        let plan = PaymentPlan::After(
+            Witness::Signature(arbiter_public_key.clone()),
+            Box::new(PaymentPlan::Payment(Payment {
+                amount: amount(100.0),
+                to: "Alice".to_string(),
+            })),
+        );
+        let conditional = UnverifiedTransaction::new(
+            TransactionType::Conditional {
+                from: "Escrow".to_string(),
+                amount: amount(100.0),
+                plan,
+            },
+            2,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(conditional.clone()).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        // Mining without the witness leaves the payment in escrow.
+        assert_eq!(blockchain.get_balance("Alice"), amount(0.0));
+
+        let tx_id = blockchain.chain.last().unwrap().transactions[0].id.clone();
+        let message = witness_apply_message(&tx_id, &arbiter_public_key);
+        let signature = sign_message(&arbiter_key, &message);
+        blockchain
+            .apply_witness(&tx_id, &signature.public_key, &signature.signature)
+            .unwrap();
+
+        assert_eq!(blockchain.get_balance("Alice"), amount(100.0));
+        assert_eq!(blockchain.get_balance("Escrow"), amount(0.0));
+    }
+
+    #[test]
+    fn test_signature_witness_survives_restart() {
+        let db_path = std::env::temp_dir()
+            .join(format!("chunk0-5-witness-roundtrip-{}.db", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = std::fs::remove_file(&db_path);
+
+        let arbiter_key = generate_keypair();
+        let arbiter_public_key = hex::encode(arbiter_key.verifying_key().to_bytes());
+        let tx_id;
+
+        {
+            let mut blockchain = Blockchain::open(&db_path, 2).unwrap();
+
+            let fund = UnverifiedTransaction::new(
+                TransactionType::Standard {
+                    from: "genesis".to_string(),
+                    to: "Escrow".to_string(),
+                    amount: amount(100.0),
+                },
+                1,
+                blockchain.current_blockhash(),
+            );
+            blockchain.add_transaction(fund).unwrap();
+            blockchain.mine_pending_transactions().unwrap();
+
+            let plan = PaymentPlan::After(
+                Witness::Signature(arbiter_public_key.clone()),
+                Box::new(PaymentPlan::Payment(Payment {
+                    amount: amount(100.0),
+                    to: "Alice".to_string(),
+                })),
+            );
+            let conditional = UnverifiedTransaction::new(
+                TransactionType::Conditional {
+                    from: "Escrow".to_string(),
+                    amount: amount(100.0),
+                    plan,
+                },
+                2,
+                blockchain.current_blockhash(),
+            );
+            blockchain.add_transaction(conditional).unwrap();
+            blockchain.mine_pending_transactions().unwrap();
+
+            tx_id = blockchain.chain.last().unwrap().transactions[0].id.clone();
+            let message = witness_apply_message(&tx_id, &arbiter_public_key);
+            let signature = sign_message(&arbiter_key, &message);
+            blockchain
+                .apply_witness(&tx_id, &signature.public_key, &signature.signature)
+                .unwrap();
+
+            assert_eq!(blockchain.get_balance("Alice"), amount(100.0));
+        }
+
+        // Re-opening the same database replays the chain from scratch, so
+        // the escrowed Conditional transaction re-enters pending_contracts -
+        // but the persisted witness should resolve it right back to paid
+        // rather than leaving the funds stuck in escrow.
+        let reopened = Blockchain::open(&db_path, 2).unwrap();
+        assert_eq!(reopened.get_balance("Alice"), amount(100.0));
+        assert_eq!(reopened.get_balance("Escrow"), amount(0.0));
+        assert!(reopened.transaction(&tx_id).is_some());
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_many_small_transfers_sum_exactly() {
+        let mut blockchain = Blockchain::new(2);
+
+        let funded = amount(1000.0);
+        let fund = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Alice".to_string(),
+                amount: funded,
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        blockchain.add_transaction(fund).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        // Thousands of transfers too small to represent exactly in binary
+        // floating point - f64 balances would drift here, but Decimal
+        // arithmetic is exact.
+        let transfer = amount(0.1);
+        for nonce in 0..10_000u64 {
+            let tx = UnverifiedTransaction::new(
+                TransactionType::Standard {
+                    from: "Alice".to_string(),
+                    to: "Bob".to_string(),
+                    amount: transfer,
+                },
+                nonce + 2,
+                blockchain.current_blockhash(),
+            );
+            blockchain.add_transaction(tx).unwrap();
+        }
+        blockchain.mine_pending_transactions().unwrap();
+
+        assert_eq!(blockchain.get_balance("Alice"), amount(0.0));
+        assert_eq!(blockchain.get_balance("Bob"), funded);
+    }
+
+    #[test]
+    fn test_block_provider_lookups() {
+        let mut blockchain = Blockchain::new(2);
+
+        let tx = UnverifiedTransaction::new(
+            TransactionType::Standard {
+                from: "genesis".to_string(),
+                to: "Alice".to_string(),
+                amount: amount(100.0),
+            },
+            1,
+            blockchain.current_blockhash(),
+        );
+        let tx_id = tx.clone().verify(&HashMap::new()).unwrap().id;
+        blockchain.add_transaction(tx).unwrap();
+        blockchain.mine_pending_transactions().unwrap();
+
+        let mined_block = blockchain.chain.last().unwrap().clone();
+
+        assert!(blockchain.is_known(&mined_block.hash));
+        assert!(!blockchain.is_known("not-a-real-hash"));
+
+        assert_eq!(
+            blockchain.block_by_hash(&mined_block.hash).unwrap().index,
+            mined_block.index
+        );
+        assert_eq!(
+            blockchain.block_by_number(mined_block.index).unwrap().hash,
+            mined_block.hash
+        );
+        assert_eq!(
+            blockchain.block_header(&mined_block.hash).unwrap().merkle_root,
+            mined_block.merkle_root
+        );
+
+        let (found_index, found_tx) = blockchain.transaction(&tx_id).unwrap();
+        assert_eq!(found_index, mined_block.index);
+        assert_eq!(found_tx.id, tx_id);
+    }
 }
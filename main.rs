@@ -3,146 +3,251 @@ use chrono::{Duration, Utc};
 
 fn main() {
     println!("🔗 Multi-Signature Blockchain with Time-Locked Transactions\n");
-    
+
     // Create blockchain with difficulty 3 (3 leading zeros in hash)
     let mut blockchain = Blockchain::new(3);
-    
+
     println!("✓ Genesis block created\n");
-    
+
     // Demo 1: Standard Transactions
     println!("=== DEMO 1: Standard Transactions ===");
-    let tx1 = Transaction::new(
+    let tx1 = UnverifiedTransaction::new(
         TransactionType::Standard {
             from: "genesis".to_string(),
             to: "Alice".to_string(),
-            amount: 1000.0,
+            amount: amount(1000.0),
         },
         1,
+        blockchain.current_blockhash(),
     );
     blockchain.add_transaction(tx1).unwrap();
-    
-    let tx2 = Transaction::new(
+
+    let tx2 = UnverifiedTransaction::new(
         TransactionType::Standard {
             from: "genesis".to_string(),
             to: "Bob".to_string(),
-            amount: 500.0,
+            amount: amount(500.0),
         },
         2,
+        blockchain.current_blockhash(),
     );
     blockchain.add_transaction(tx2).unwrap();
-    
+
     println!("Mining block with 2 transactions...");
-    blockchain.mine_pending_transactions();
+    blockchain.mine_pending_transactions().unwrap();
     println!("✓ Block mined successfully\n");
-    
+
     // Demo 2: Multi-Signature Wallet
     println!("=== DEMO 2: Multi-Signature Wallet ===");
     println!("Creating a 2-of-3 multisig wallet for company treasury...");
-    
+
+    // Each executive holds a real ed25519 keypair; the wallet is
+    // authorized by public key rather than by name.
+    let ceo_key = generate_keypair();
+    let cfo_key = generate_keypair();
+    let cto_key = generate_keypair();
+
     blockchain.create_multisig_wallet(
         "company_treasury".to_string(),
         vec![
-            "CEO".to_string(),
-            "CFO".to_string(),
-            "CTO".to_string(),
+            hex::encode(ceo_key.verifying_key().to_bytes()),
+            hex::encode(cfo_key.verifying_key().to_bytes()),
+            hex::encode(cto_key.verifying_key().to_bytes()),
         ],
-    );
-    
+    ).unwrap();
+
     // Fund the treasury
-    let tx3 = Transaction::new(
+    let tx3 = UnverifiedTransaction::new(
         TransactionType::Standard {
             from: "genesis".to_string(),
             to: "company_treasury".to_string(),
-            amount: 10000.0,
+            amount: amount(10000.0),
         },
         3,
+        blockchain.current_blockhash(),
     );
     blockchain.add_transaction(tx3).unwrap();
-    blockchain.mine_pending_transactions();
-    
+    blockchain.mine_pending_transactions().unwrap();
+
     println!("✓ Treasury funded with 10,000 units\n");
-    
+
     // Attempt multisig withdrawal
     println!("Attempting withdrawal requiring 2 signatures (CEO + CFO)...");
-    let tx4 = Transaction::new(
-        TransactionType::MultiSig {
-            from: "company_treasury".to_string(),
-            to: "Vendor".to_string(),
-            amount: 3000.0,
-            required_signatures: 2,
-            signatures: vec!["CEO".to_string(), "CFO".to_string()],
-        },
-        4,
+    let from = "company_treasury".to_string();
+    let to = "Vendor".to_string();
+    let transfer_amount = amount(3000.0);
+    let required_signatures = 2;
+    let timestamp = Utc::now();
+    let nonce = 4;
+    let recent_blockhash = blockchain.current_blockhash();
+    let message = multisig_signing_message(
+        &from,
+        &to,
+        transfer_amount,
+        required_signatures,
+        timestamp,
+        nonce,
+        &recent_blockhash,
     );
+
+    let tx4 = UnverifiedTransaction {
+        tx_type: TransactionType::MultiSig {
+            from,
+            to,
+            amount: transfer_amount,
+            required_signatures,
+            signatures: vec![
+                sign_message(&ceo_key, &message),
+                sign_message(&cfo_key, &message),
+            ],
+        },
+        timestamp,
+        nonce,
+        recent_blockhash,
+    };
     blockchain.add_transaction(tx4).unwrap();
-    blockchain.mine_pending_transactions();
+    blockchain.mine_pending_transactions().unwrap();
     println!("✓ Multisig transaction approved and executed\n");
-    
+
     // Demo 3: Time-Locked Transaction
     println!("=== DEMO 3: Time-Locked Transactions ===");
     println!("Creating vesting schedule for employee stock options...");
-    
+
     // Transaction that unlocks in 1 year (simulated as past for demo)
     let unlock_time = Utc::now() - Duration::days(1); // Past date for demo
-    let tx5 = Transaction::new(
+    let tx5 = UnverifiedTransaction::new(
         TransactionType::TimeLocked {
             from: "company_treasury".to_string(),
             to: "Employee_John".to_string(),
-            amount: 1000.0,
+            amount: amount(1000.0),
             unlock_time,
         },
         5,
+        blockchain.current_blockhash(),
     );
-    
+
     println!("Transaction will unlock at: {}", unlock_time);
     blockchain.add_transaction(tx5).unwrap();
-    blockchain.mine_pending_transactions();
+    blockchain.mine_pending_transactions().unwrap();
     println!("✓ Time-locked transaction executed (vesting period passed)\n");
-    
+
     // Demo 4: Transaction Validation
     println!("=== DEMO 4: Transaction Validation ===");
-    
+
     // Try insufficient signatures
     println!("Attempting multisig with only 1 signature (should fail)...");
-    let tx6 = Transaction::new(
-        TransactionType::MultiSig {
-            from: "company_treasury".to_string(),
-            to: "Hacker".to_string(),
-            amount: 5000.0,
-            required_signatures: 2,
-            signatures: vec!["CEO".to_string()], // Only 1 signature
-        },
-        6,
+    let from = "company_treasury".to_string();
+    let to = "Hacker".to_string();
+    let transfer_amount = amount(5000.0);
+    let required_signatures = 2;
+    let timestamp = Utc::now();
+    let nonce = 6;
+    let recent_blockhash = blockchain.current_blockhash();
+    let message = multisig_signing_message(
+        &from,
+        &to,
+        transfer_amount,
+        required_signatures,
+        timestamp,
+        nonce,
+        &recent_blockhash,
     );
-    
+
+    let tx6 = UnverifiedTransaction {
+        tx_type: TransactionType::MultiSig {
+            from,
+            to,
+            amount: transfer_amount,
+            required_signatures,
+            signatures: vec![sign_message(&ceo_key, &message)], // Only 1 signature
+        },
+        timestamp,
+        nonce,
+        recent_blockhash,
+    };
+
     match blockchain.add_transaction(tx6) {
         Ok(_) => println!("Transaction added"),
         Err(e) => println!("✓ Transaction rejected: {}", e),
     }
-    
+
     // Try future-locked transaction
     println!("\nAttempting time-locked transaction (future unlock)...");
     let future_unlock = Utc::now() + Duration::days(365);
-    let tx7 = Transaction::new(
+    let tx7 = UnverifiedTransaction::new(
         TransactionType::TimeLocked {
             from: "Alice".to_string(),
             to: "Bob".to_string(),
-            amount: 100.0,
+            amount: amount(100.0),
             unlock_time: future_unlock,
         },
         7,
+        blockchain.current_blockhash(),
     );
-    
+
     match blockchain.add_transaction(tx7) {
         Ok(_) => println!("Transaction added"),
         Err(e) => println!("✓ Transaction rejected: {}", e),
     }
     println!();
-    
-    // Demo 5: Chain Validation
-    println!("=== DEMO 5: Blockchain Validation ===");
+
+    // Demo 5: Conditional Payments (Escrow)
+    println!("=== DEMO 5: Conditional Payment Plans ===");
+    println!("Funding an escrow account for a freelance contract...");
+
+    let tx8 = UnverifiedTransaction::new(
+        TransactionType::Standard {
+            from: "genesis".to_string(),
+            to: "Escrow".to_string(),
+            amount: amount(2000.0),
+        },
+        8,
+        blockchain.current_blockhash(),
+    );
+    blockchain.add_transaction(tx8).unwrap();
+    blockchain.mine_pending_transactions().unwrap();
+
+    // The contractor is paid once an arbiter signs off on the work.
+    let arbiter_key = generate_keypair();
+    let arbiter_public_key = hex::encode(arbiter_key.verifying_key().to_bytes());
+    let plan = PaymentPlan::After(
+        Witness::Signature(arbiter_public_key.clone()),
+        Box::new(PaymentPlan::Payment(Payment {
+            amount: amount(2000.0),
+            to: "Contractor".to_string(),
+        })),
+    );
+    let tx9 = UnverifiedTransaction::new(
+        TransactionType::Conditional {
+            from: "Escrow".to_string(),
+            amount: amount(2000.0),
+            plan,
+        },
+        9,
+        blockchain.current_blockhash(),
+    );
+    blockchain.add_transaction(tx9).unwrap();
+    blockchain.mine_pending_transactions().unwrap();
+    println!(
+        "Contractor balance before sign-off: {}",
+        blockchain.get_balance("Contractor")
+    );
+
+    let tx_id = blockchain.chain.last().unwrap().transactions[0].id.clone();
+    let message = witness_apply_message(&tx_id, &arbiter_public_key);
+    let signature = sign_message(&arbiter_key, &message);
+    blockchain
+        .apply_witness(&tx_id, &signature.public_key, &signature.signature)
+        .unwrap();
+    println!(
+        "✓ Arbiter signed off - contractor balance now: {}\n",
+        blockchain.get_balance("Contractor")
+    );
+
+    // Demo 6: Chain Validation
+    println!("=== DEMO 6: Blockchain Validation ===");
     println!("Validating entire blockchain integrity...");
-    
+
     if blockchain.is_chain_valid() {
         println!("✓ Blockchain is valid!");
         println!("  - All hashes verified");
@@ -151,10 +256,27 @@ fn main() {
     } else {
         println!("✗ Blockchain validation failed!");
     }
-    
+
+    // Demo 7: BlockProvider Lookups
+    println!("\n=== DEMO 7: BlockProvider Lookups ===");
+    let latest_hash = blockchain.current_blockhash();
+    println!("Looking up the latest block by hash...");
+    if let Some(header) = blockchain.block_header(&latest_hash) {
+        println!(
+            "✓ Found block #{} (merkle root: {})",
+            header.index, header.merkle_root
+        );
+    }
+    if let Some((block_index, found_tx)) = blockchain.transaction(&tx_id) {
+        println!(
+            "✓ Found conditional transaction {} in block #{}",
+            found_tx.id, block_index
+        );
+    }
+
     // Print final state
     blockchain.print_chain();
-    
+
     // Summary
     println!("\n=== BLOCKCHAIN SUMMARY ===");
     println!("Total Blocks: {}", blockchain.chain.len());
@@ -162,11 +284,13 @@ fn main() {
     println!("Total Accounts: {}", blockchain.balances.len());
     println!("\nKey Features Demonstrated:");
     println!("  ✓ Proof of Work mining");
-    println!("  ✓ Multi-signature wallets (2-of-3)");
+    println!("  ✓ Cryptographic multi-signature wallets (2-of-3)");
     println!("  ✓ Time-locked transactions (vesting)");
+    println!("  ✓ Conditional payment plans (escrow)");
     println!("  ✓ Transaction validation");
     println!("  ✓ Chain integrity verification");
     println!("  ✓ Balance tracking");
-    
+    println!("  ✓ O(1) block/transaction lookups (BlockProvider)");
+
     println!("\n🎉 Blockchain demonstration complete!");
 }
@@ -0,0 +1,76 @@
+//! A Merkle tree over a block's transaction ids, built the way Bitcoin
+//! does: SHA-256 leaves, pairwise hashing up each level, and the last leaf
+//! duplicated whenever a level has an odd number of nodes. This lets a
+//! light client prove a transaction was included in a block without
+//! needing every other transaction in it.
+
+use sha2::{Digest, Sha256};
+
+fn hash_leaf(tx_id: &str) -> String {
+    hex::encode(Sha256::digest(tx_id.as_bytes()))
+}
+
+fn hash_pair(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// The Merkle root of a set of transaction ids. An empty block hashes to
+/// the SHA-256 of the empty string, so it still has a well-defined root.
+pub fn root(tx_ids: &[String]) -> String {
+    if tx_ids.is_empty() {
+        return hex::encode(Sha256::digest(b""));
+    }
+
+    let mut level: Vec<String> = tx_ids.iter().map(|id| hash_leaf(id)).collect();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level.last().unwrap().clone());
+        }
+        level = level.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect();
+    }
+    level.remove(0)
+}
+
+/// A Merkle proof: the sibling hash at each level on the path from a leaf
+/// to the root, paired with whether that sibling sits to the left (so a
+/// verifier knows which order to concatenate before hashing).
+pub fn proof(tx_id: &str, tx_ids: &[String]) -> Option<Vec<(String, bool)>> {
+    let leaf_hash = hash_leaf(tx_id);
+    let mut level: Vec<String> = tx_ids.iter().map(|id| hash_leaf(id)).collect();
+    let mut index = level.iter().position(|hash| *hash == leaf_hash)?;
+
+    let mut path = Vec::new();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level.last().unwrap().clone());
+        }
+
+        let sibling_is_left = index % 2 == 1;
+        let sibling_index = if sibling_is_left { index - 1 } else { index + 1 };
+        path.push((level[sibling_index].clone(), sibling_is_left));
+
+        level = level.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect();
+        index /= 2;
+    }
+
+    Some(path)
+}
+
+/// Recomputes the root implied by `proof` starting from `tx_id` and checks
+/// it against `root`. This is all a light client needs to trust that a
+/// transaction is in a block, without holding the block's other
+/// transactions.
+pub fn verify_proof(tx_id: &str, proof: &[(String, bool)], root: &str) -> bool {
+    let mut hash = hash_leaf(tx_id);
+    for (sibling, sibling_is_left) in proof {
+        hash = if *sibling_is_left {
+            hash_pair(sibling, &hash)
+        } else {
+            hash_pair(&hash, sibling)
+        };
+    }
+    hash == root
+}
@@ -0,0 +1,91 @@
+//! Conditional payments, modeled after Solana's Budget program payment
+//! plans. A `PaymentPlan` is a tree of `Witness` conditions wrapping a
+//! final `Payment`; it only resolves - releasing the payment - once every
+//! witness on the path to that payment has cleared.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A condition a `PaymentPlan` can be gated on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Witness {
+    /// Clears once the current time reaches or passes this timestamp.
+    Timestamp(DateTime<Utc>),
+    /// Clears once a matching signed "apply" message arrives from this
+    /// signer (identified by hex-encoded ed25519 public key).
+    Signature(String),
+}
+
+impl Witness {
+    fn is_satisfied(&self, current_time: DateTime<Utc>, satisfied_signatures: &HashSet<String>) -> bool {
+        match self {
+            Witness::Timestamp(unlock_time) => current_time >= *unlock_time,
+            Witness::Signature(public_key) => satisfied_signatures.contains(public_key),
+        }
+    }
+}
+
+/// The payment a `PaymentPlan` releases once it resolves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payment {
+    pub amount: Decimal,
+    pub to: String,
+}
+
+/// A tree of witnessed conditions guarding a `Payment`. Unlike a single
+/// fixed unlock timestamp, this composes: a payment can be released after
+/// a deadline, after both a deadline and a signature, or via whichever of
+/// two branches clears first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaymentPlan {
+    /// No remaining conditions - resolves immediately.
+    Payment(Payment),
+    /// Resolves to `plan` once `witness` clears.
+    After(Witness, Box<PaymentPlan>),
+    /// Resolves to `plan` once both witnesses have cleared.
+    And(Witness, Witness, Box<PaymentPlan>),
+    /// Resolves to whichever branch's witness clears first.
+    Or((Witness, Box<PaymentPlan>), (Witness, Box<PaymentPlan>)),
+}
+
+impl PaymentPlan {
+    /// Attempts to resolve the plan given the current time and the set of
+    /// signature witnesses satisfied so far. Returns the final `Payment`
+    /// once every condition on the path to it has cleared.
+    pub fn resolve(
+        &self,
+        current_time: DateTime<Utc>,
+        satisfied_signatures: &HashSet<String>,
+    ) -> Option<&Payment> {
+        match self {
+            PaymentPlan::Payment(payment) => Some(payment),
+            PaymentPlan::After(witness, plan) => {
+                if witness.is_satisfied(current_time, satisfied_signatures) {
+                    plan.resolve(current_time, satisfied_signatures)
+                } else {
+                    None
+                }
+            }
+            PaymentPlan::And(first, second, plan) => {
+                if first.is_satisfied(current_time, satisfied_signatures)
+                    && second.is_satisfied(current_time, satisfied_signatures)
+                {
+                    plan.resolve(current_time, satisfied_signatures)
+                } else {
+                    None
+                }
+            }
+            PaymentPlan::Or((left_witness, left_plan), (right_witness, right_plan)) => {
+                if left_witness.is_satisfied(current_time, satisfied_signatures) {
+                    left_plan.resolve(current_time, satisfied_signatures)
+                } else if right_witness.is_satisfied(current_time, satisfied_signatures) {
+                    right_plan.resolve(current_time, satisfied_signatures)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Persistence for the blockchain. Following the Alfis approach of saving
+//! the chain to SQLite rather than keeping it purely in memory, this module
+//! defines a `Storage` trait the `Blockchain` writes through, plus two
+//! implementations: `SqliteStorage` for real persistence and
+//! `InMemoryStorage` so the existing test suite keeps running without
+//! touching the filesystem.
+
+use crate::{Block, Transaction};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    idx INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    previous_hash TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    merkle_root TEXT NOT NULL,
+    transactions TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS wallet_signers (
+    wallet_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    PRIMARY KEY (wallet_id, public_key)
+);
+CREATE TABLE IF NOT EXISTS witnesses (
+    tx_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    PRIMARY KEY (tx_id, public_key)
+);
+";
+
+/// Persists mined blocks, multisig wallet signer sets, and applied
+/// `Conditional` transaction witnesses. `Blockchain` never touches the
+/// backing store directly - it only goes through this trait, so swapping
+/// SQLite for something else is a one-line change.
+pub trait Storage {
+    fn save_block(&mut self, block: &Block) -> Result<(), String>;
+    fn load_chain(&self) -> Result<Vec<Block>, String>;
+    fn save_wallet(&mut self, wallet_id: &str, authorized_signers: &[String]) -> Result<(), String>;
+    fn load_wallets(&self) -> Result<HashMap<String, Vec<String>>, String>;
+    /// Records that `public_key` witnessed `tx_id`, so a signature witness
+    /// (unlike a `Witness::Timestamp`, which always re-evaluates the same
+    /// way) survives a restart and replay.
+    fn save_witness(&mut self, tx_id: &str, public_key: &str, signature: &str) -> Result<(), String>;
+    /// Every `(tx_id, public_key, signature)` witness ever applied, in the
+    /// order `apply_witness` saw them.
+    fn load_witnesses(&self) -> Result<Vec<(String, String, String)>, String>;
+}
+
+/// Persists the chain to a SQLite database file: one row per block in
+/// `blocks` (transactions stored as a JSON blob), and one row per
+/// `(wallet_id, public_key)` pair in `wallet_signers`.
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        Ok(SqliteStorage { conn })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn save_block(&mut self, block: &Block) -> Result<(), String> {
+        let transactions_json =
+            serde_json::to_string(&block.transactions).map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO blocks (idx, timestamp, previous_hash, hash, nonce, merkle_root, transactions)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    block.index as i64,
+                    block.timestamp.to_rfc3339(),
+                    block.previous_hash,
+                    block.hash,
+                    block.nonce as i64,
+                    block.merkle_root,
+                    transactions_json,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn load_chain(&self) -> Result<Vec<Block>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT idx, timestamp, previous_hash, hash, nonce, merkle_root, transactions
+                 FROM blocks ORDER BY idx ASC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut blocks = Vec::new();
+        for row in rows {
+            let (idx, timestamp, previous_hash, hash, nonce, merkle_root, transactions_json) =
+                row.map_err(|e| e.to_string())?;
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+                .map_err(|e| e.to_string())?
+                .with_timezone(&Utc);
+            let transactions: Vec<Transaction> =
+                serde_json::from_str(&transactions_json).map_err(|e| e.to_string())?;
+            blocks.push(Block {
+                index: idx as u64,
+                timestamp,
+                transactions,
+                previous_hash,
+                merkle_root,
+                hash,
+                nonce: nonce as u64,
+            });
+        }
+        Ok(blocks)
+    }
+
+    fn save_wallet(&mut self, wallet_id: &str, authorized_signers: &[String]) -> Result<(), String> {
+        self.conn
+            .execute(
+                "DELETE FROM wallet_signers WHERE wallet_id = ?1",
+                params![wallet_id],
+            )
+            .map_err(|e| e.to_string())?;
+        for public_key in authorized_signers {
+            self.conn
+                .execute(
+                    "INSERT INTO wallet_signers (wallet_id, public_key) VALUES (?1, ?2)",
+                    params![wallet_id, public_key],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn load_wallets(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT wallet_id, public_key FROM wallet_signers")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+
+        let mut wallets: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (wallet_id, public_key) = row.map_err(|e| e.to_string())?;
+            wallets.entry(wallet_id).or_default().push(public_key);
+        }
+        Ok(wallets)
+    }
+
+    fn save_witness(&mut self, tx_id: &str, public_key: &str, signature: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO witnesses (tx_id, public_key, signature) VALUES (?1, ?2, ?3)",
+                params![tx_id, public_key, signature],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn load_witnesses(&self) -> Result<Vec<(String, String, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tx_id, public_key, signature FROM witnesses ORDER BY rowid ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut witnesses = Vec::new();
+        for row in rows {
+            witnesses.push(row.map_err(|e| e.to_string())?);
+        }
+        Ok(witnesses)
+    }
+}
+
+/// Keeps blocks, wallet signer sets, and witnesses in memory only. Used by
+/// `Blockchain::new` so the existing in-process test suite doesn't need a
+/// database file on disk.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    blocks: Vec<Block>,
+    wallets: HashMap<String, Vec<String>>,
+    witnesses: Vec<(String, String, String)>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        InMemoryStorage::default()
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn save_block(&mut self, block: &Block) -> Result<(), String> {
+        self.blocks.push(block.clone());
+        Ok(())
+    }
+
+    fn load_chain(&self) -> Result<Vec<Block>, String> {
+        Ok(self.blocks.clone())
+    }
+
+    fn save_wallet(&mut self, wallet_id: &str, authorized_signers: &[String]) -> Result<(), String> {
+        self.wallets
+            .insert(wallet_id.to_string(), authorized_signers.to_vec());
+        Ok(())
+    }
+
+    fn load_wallets(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        Ok(self.wallets.clone())
+    }
+
+    fn save_witness(&mut self, tx_id: &str, public_key: &str, signature: &str) -> Result<(), String> {
+        self.witnesses
+            .push((tx_id.to_string(), public_key.to_string(), signature.to_string()));
+        Ok(())
+    }
+
+    fn load_witnesses(&self) -> Result<Vec<(String, String, String)>, String> {
+        Ok(self.witnesses.clone())
+    }
+}